@@ -0,0 +1,376 @@
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex as StdMutex};
+use std::time::Duration;
+
+use tokio::sync::{Mutex, OwnedSemaphorePermit, Semaphore};
+
+/// How long we wait for an engine to answer a probe (`info`) or reset
+/// (`newgame`) before declaring it dead and discarding it.
+const HEALTH_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// A single UHP engine child process together with the ends of its pipes.
+///
+/// An engine is reset between games with `newgame` rather than being killed, so
+/// the expensive fork/exec only happens once per pooled slot.
+pub struct Engine {
+    child: Arc<StdMutex<Child>>,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+}
+
+/// A cheap clonable handle that can kill an engine's process from another task,
+/// used to unblock a wedged reader when a probe times out.
+#[derive(Clone)]
+struct Killer(Arc<StdMutex<Child>>);
+
+impl Killer {
+    fn kill(&self) {
+        if let Ok(mut child) = self.0.lock() {
+            let _ = child.kill();
+        }
+    }
+}
+
+impl Engine {
+    /// Spawn a fresh engine from a shell-style `ai_command` such as
+    /// `"../nokamute/target/debug/nokamute uhp --threads=1"`.
+    pub fn spawn(ai_command: &str) -> std::io::Result<Engine> {
+        let mut parts = ai_command.split_whitespace();
+        let program = parts
+            .next()
+            .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidInput, "empty ai_command"))?;
+
+        let mut child = Command::new(program)
+            .args(parts)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+
+        let stdin = child.stdin.take().expect("child stdin was piped");
+        let stdout = BufReader::new(child.stdout.take().expect("child stdout was piped"));
+        Ok(Engine {
+            child: Arc::new(StdMutex::new(child)),
+            stdin,
+            stdout,
+        })
+    }
+
+    fn killer(&self) -> Killer {
+        Killer(self.child.clone())
+    }
+
+    /// Send a single UHP command and read lines until the engine prints `ok`,
+    /// returning the payload lines that preceded it.
+    fn command(&mut self, command: &str) -> std::io::Result<Vec<String>> {
+        writeln!(self.stdin, "{}", command)?;
+        self.stdin.flush()?;
+
+        let mut lines = Vec::new();
+        loop {
+            let mut line = String::new();
+            if self.stdout.read_line(&mut line)? == 0 {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::UnexpectedEof,
+                    "engine closed stdout",
+                ));
+            }
+            let trimmed = line.trim_end();
+            if trimmed == "ok" {
+                break;
+            }
+            lines.push(trimmed.to_string());
+        }
+        Ok(lines)
+    }
+
+    /// Reset the engine to a given game position, reusing the running process.
+    fn newgame(&mut self, game_string: &str) -> std::io::Result<()> {
+        self.command(&format!("newgame {}", game_string))?;
+        Ok(())
+    }
+
+    /// Ask the engine for the best move in the current position.
+    fn bestmove(&mut self, args: &str) -> std::io::Result<String> {
+        let lines = self.command(&format!("bestmove {}", args))?;
+        Ok(lines.last().cloned().unwrap_or_default())
+    }
+}
+
+impl Drop for Engine {
+    fn drop(&mut self) {
+        if let Ok(mut child) = self.child.lock() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+/// Run a blocking engine interaction `f` off the async runtime via
+/// `spawn_blocking`, optionally bounded by `timeout`. On timeout the engine's
+/// process is killed so the wedged reader unblocks, and `None` is returned in
+/// place of the (now discarded) engine.
+async fn on_blocking<T, F>(
+    mut engine: Engine,
+    timeout: Option<Duration>,
+    f: F,
+) -> (Option<Engine>, std::io::Result<T>)
+where
+    T: Send + 'static,
+    F: FnOnce(&mut Engine) -> std::io::Result<T> + Send + 'static,
+{
+    let killer = engine.killer();
+    let handle = tokio::task::spawn_blocking(move || {
+        let result = f(&mut engine);
+        (engine, result)
+    });
+
+    match timeout {
+        Some(duration) => match tokio::time::timeout(duration, handle).await {
+            Ok(joined) => {
+                let (engine, result) = joined.expect("engine blocking task panicked");
+                (Some(engine), result)
+            }
+            Err(_) => {
+                // Kill the child so the blocked read returns and the task (and
+                // the engine it owns) drops; the engine is not returned.
+                killer.kill();
+                (
+                    None,
+                    Err(std::io::Error::new(
+                        std::io::ErrorKind::TimedOut,
+                        "engine did not respond within timeout",
+                    )),
+                )
+            }
+        },
+        None => {
+            let (engine, result) = handle.await.expect("engine blocking task panicked");
+            (Some(engine), result)
+        }
+    }
+}
+
+async fn spawn_engine(ai_command: &str) -> std::io::Result<Engine> {
+    let ai_command = ai_command.to_string();
+    tokio::task::spawn_blocking(move || Engine::spawn(&ai_command))
+        .await
+        .expect("engine spawn task panicked")
+}
+
+/// A pool of warm engines keyed by bot name. Each bot caps the number of live
+/// processes; checking one out blocks (rather than spawning unbounded) when the
+/// cap is reached.
+#[derive(Clone)]
+pub struct EnginePool {
+    inner: Arc<Mutex<HashMap<String, BotPool>>>,
+    max_per_bot: usize,
+}
+
+struct BotPool {
+    semaphore: Arc<Semaphore>,
+    idle: Vec<Engine>,
+}
+
+/// An engine checked out of the pool. Dropping it returns the engine to its
+/// bot's idle list and releases the permit.
+pub struct PooledEngine {
+    engine: Option<Engine>,
+    bot_name: String,
+    pool: EnginePool,
+    _permit: OwnedSemaphorePermit,
+}
+
+impl EnginePool {
+    pub fn new(max_per_bot: usize) -> EnginePool {
+        EnginePool {
+            inner: Arc::new(Mutex::new(HashMap::new())),
+            max_per_bot,
+        }
+    }
+
+    /// Check out an engine for `bot_name`, spawning `ai_command` lazily if no
+    /// idle engine is available and the cap has not been reached. Reused engines
+    /// pass an `info` health probe (under [`HEALTH_TIMEOUT`]) and are reset with
+    /// `newgame` before being handed back.
+    pub async fn checkout(
+        &self,
+        bot_name: &str,
+        ai_command: &str,
+        game_string: &str,
+    ) -> std::io::Result<PooledEngine> {
+        let semaphore = {
+            let mut pools = self.inner.lock().await;
+            pools
+                .entry(bot_name.to_string())
+                .or_insert_with(|| BotPool {
+                    semaphore: Arc::new(Semaphore::new(self.max_per_bot)),
+                    idle: Vec::new(),
+                })
+                .semaphore
+                .clone()
+        };
+
+        let permit = semaphore
+            .acquire_owned()
+            .await
+            .expect("engine pool semaphore closed");
+
+        let engine = self.acquire_healthy(bot_name, ai_command).await?;
+
+        // Reset state for the new game; if it times out or fails, `reset`
+        // discards the engine, so spawn a replacement and try once more.
+        let game_string = game_string.to_string();
+        let engine = match reset(engine, &game_string).await {
+            Some(engine) => engine,
+            None => {
+                let fresh = spawn_engine(ai_command).await?;
+                reset(fresh, &game_string)
+                    .await
+                    .ok_or_else(|| {
+                        std::io::Error::new(
+                            std::io::ErrorKind::Other,
+                            "freshly spawned engine failed newgame",
+                        )
+                    })?
+            }
+        };
+
+        Ok(PooledEngine {
+            engine: Some(engine),
+            bot_name: bot_name.to_string(),
+            pool: self.clone(),
+            _permit: permit,
+        })
+    }
+
+    /// Pop idle engines until one passes its health probe, or spawn a fresh one.
+    async fn acquire_healthy(
+        &self,
+        bot_name: &str,
+        ai_command: &str,
+    ) -> std::io::Result<Engine> {
+        loop {
+            let candidate = {
+                let mut pools = self.inner.lock().await;
+                pools.get_mut(bot_name).and_then(|pool| pool.idle.pop())
+            };
+            match candidate {
+                Some(engine) => {
+                    let (engine, result) =
+                        on_blocking(engine, Some(HEALTH_TIMEOUT), |e| e.command("info").map(|_| ())).await;
+                    if let (Some(engine), Ok(())) = (engine, result) {
+                        return Ok(engine);
+                    }
+                    // Unhealthy or timed-out engine: discarded, try the next one.
+                }
+                None => return spawn_engine(ai_command).await,
+            }
+        }
+    }
+
+    fn release(&self, bot_name: &str, engine: Engine) {
+        // Best-effort, synchronous return on drop; use a non-blocking try_lock
+        // since we are not in an async context here.
+        if let Ok(mut pools) = self.inner.try_lock() {
+            if let Some(pool) = pools.get_mut(bot_name) {
+                pool.idle.push(engine);
+            }
+        }
+        // If the lock is contended the engine is simply dropped (and killed),
+        // which is safe — the pool will spawn a fresh one next time.
+    }
+}
+
+/// Reset an engine to `game_string` under [`HEALTH_TIMEOUT`], returning the
+/// engine on success or `None` if it timed out / errored (in which case it has
+/// been discarded).
+async fn reset(engine: Engine, game_string: &str) -> Option<Engine> {
+    let game_string = game_string.to_string();
+    let (engine, result) =
+        on_blocking(engine, Some(HEALTH_TIMEOUT), move |e| e.newgame(&game_string)).await;
+    match (engine, result) {
+        (Some(engine), Ok(())) => Some(engine),
+        _ => None,
+    }
+}
+
+impl PooledEngine {
+    /// Discard this engine instead of returning it to the pool, e.g. after a
+    /// failed move, so a fresh process is spawned next time.
+    pub fn discard(mut self) {
+        self.engine.take();
+    }
+}
+
+impl Drop for PooledEngine {
+    fn drop(&mut self) {
+        if let Some(engine) = self.engine.take() {
+            self.pool.release(&self.bot_name, engine);
+        }
+    }
+}
+
+/// Compute a bestmove for a game position, reusing a warm engine from `pool`.
+/// The blocking engine search runs on `spawn_blocking` so it does not starve the
+/// async executor. If `cancel` is set while the search is running, the engine's
+/// process is killed to abort it and an `Interrupted` error is returned.
+pub async fn run_commands(
+    pool: &EnginePool,
+    bot_name: &str,
+    ai_command: &str,
+    game_string: &str,
+    bestmove_command_args: &str,
+    cancel: Arc<AtomicBool>,
+) -> std::io::Result<String> {
+    let mut pooled = pool.checkout(bot_name, ai_command, game_string).await?;
+    let engine = pooled.engine.take().expect("engine checked out");
+
+    let killer = engine.killer();
+    let args = bestmove_command_args.to_string();
+    let mut handle = tokio::task::spawn_blocking(move || {
+        let result = engine.bestmove(&args);
+        (engine, result)
+    });
+
+    // Poll the cancellation flag while the search runs; killing the child
+    // unblocks the reader and lets the blocking task (and its engine) finish.
+    let (engine, result) = loop {
+        tokio::select! {
+            joined = &mut handle => {
+                let (engine, result) = joined.expect("engine blocking task panicked");
+                break (Some(engine), result);
+            }
+            _ = tokio::time::sleep(Duration::from_millis(100)) => {
+                if cancel.load(Ordering::Relaxed) {
+                    killer.kill();
+                    let _ = (&mut handle).await;
+                    break (
+                        None,
+                        Err(std::io::Error::new(
+                            std::io::ErrorKind::Interrupted,
+                            "search cancelled",
+                        )),
+                    );
+                }
+            }
+        }
+    };
+
+    match result {
+        Ok(bestmove) => {
+            // Return the healthy engine to the pool for reuse.
+            pooled.engine = engine;
+            Ok(bestmove)
+        }
+        Err(e) => {
+            // Drop the engine rather than returning it; a fresh one spawns next
+            // time.
+            drop(engine);
+            Err(e)
+        }
+    }
+}
@@ -0,0 +1,150 @@
+use std::path::Path;
+
+use serde::Deserialize;
+
+/// Where a bot's API credential comes from.
+///
+/// A plain `api_key = "..."` in TOML deserializes to [`TokenSource::Static`];
+/// a `[bots.api_key]` table with refresh fields deserializes to
+/// [`TokenSource::Refreshable`], so existing static-key configs keep working
+/// unchanged.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(untagged)]
+pub enum TokenSource {
+    Static(String),
+    Refreshable {
+        access_token: String,
+        /// Absolute expiry of `access_token` as a Unix timestamp (seconds).
+        expires_at: i64,
+        refresh_token: String,
+        /// Endpoint the refresh request is POSTed to.
+        refresh_url: String,
+    },
+}
+
+/// A single bot definition plus the engine invocation used to compute its moves.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Bot {
+    pub name: String,
+    pub uri: String,
+    pub api_key: TokenSource,
+    pub ai_command: String,
+    pub bestmove_command_args: String,
+    /// Polling interval (seconds) used when the bot is busy; the floor the
+    /// adaptive backoff snaps back to as soon as new turns or an error appear.
+    #[serde(default = "default_base_interval_secs")]
+    pub base_interval_secs: u64,
+    /// Upper bound (seconds) the interval stretches to while the bot is idle.
+    #[serde(default = "default_max_interval_secs")]
+    pub max_interval_secs: u64,
+    /// How aggressively the interval grows on idle cycles; `1.0` disables
+    /// backoff, larger values lengthen the interval faster.
+    #[serde(default = "default_tranquility")]
+    pub tranquility: f64,
+}
+
+/// Top-level configuration loaded from the TOML file passed via `--config`.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Config {
+    pub base_url: String,
+    #[serde(default = "default_max_concurrent")]
+    pub max_concurrent: usize,
+    #[serde(default = "default_queue_capacity")]
+    pub queue_capacity: usize,
+    /// Optional Postgres connection string enabling durable run state. When
+    /// absent the in-memory [`crate::turn_tracker::TurnTracker`] is the only
+    /// dedup store.
+    #[serde(default)]
+    pub database_url: Option<String>,
+    /// Age after which a stuck `Processing` row is reset to `Failed` on
+    /// startup, so the producer re-queues it on its next poll.
+    #[serde(default = "default_stale_after_secs")]
+    pub stale_after_secs: u64,
+    /// Development flag: poll the placeholder `fake_get_games` (which returns no
+    /// games) instead of the real endpoint. Off by default so the real poll and
+    /// submit path run.
+    #[serde(default)]
+    pub dev_fake_poll: bool,
+    pub bots: Vec<Bot>,
+}
+
+fn default_max_concurrent() -> usize {
+    5
+}
+
+fn default_queue_capacity() -> usize {
+    1000
+}
+
+fn default_stale_after_secs() -> u64 {
+    300
+}
+
+fn default_base_interval_secs() -> u64 {
+    1
+}
+
+fn default_max_interval_secs() -> u64 {
+    30
+}
+
+fn default_tranquility() -> f64 {
+    2.0
+}
+
+impl Config {
+    /// Load and parse a [`Config`] from a TOML file on disk.
+    pub fn load(path: &Path) -> Result<Config, Box<dyn std::error::Error + Send + Sync>> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read config file {}: {}", path.display(), e))?;
+        let config: Config = toml::from_str(&contents)
+            .map_err(|e| format!("Failed to parse config file {}: {}", path.display(), e))?;
+        Ok(config)
+    }
+
+    /// Check that every bot's `ai_command` points at a binary that exists and is
+    /// executable. Returns the list of problems found so `validate-config` can
+    /// report all of them at once rather than failing on the first.
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        for bot in &self.bots {
+            let program = match bot.ai_command.split_whitespace().next() {
+                Some(program) => program,
+                None => {
+                    problems.push(format!("Bot '{}' has an empty ai_command", bot.name));
+                    continue;
+                }
+            };
+            if let Err(reason) = check_executable(program) {
+                problems.push(format!(
+                    "Bot '{}' ai_command '{}' is not runnable: {}",
+                    bot.name, program, reason
+                ));
+            }
+        }
+        problems
+    }
+}
+
+#[cfg(unix)]
+fn check_executable(program: &str) -> Result<(), String> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let metadata = std::fs::metadata(program).map_err(|e| e.to_string())?;
+    if !metadata.is_file() {
+        return Err("not a file".to_string());
+    }
+    if metadata.permissions().mode() & 0o111 == 0 {
+        return Err("missing executable permission".to_string());
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn check_executable(program: &str) -> Result<(), String> {
+    let metadata = std::fs::metadata(program).map_err(|e| e.to_string())?;
+    if !metadata.is_file() {
+        return Err("not a file".to_string());
+    }
+    Ok(())
+}
@@ -0,0 +1,185 @@
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::Deserialize;
+use tokio::sync::Mutex;
+
+use crate::config::TokenSource;
+
+type ApiResult<T> = Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+/// Refresh a token once it is within this window of expiry.
+const REFRESH_WINDOW: Duration = Duration::from_secs(15 * 60);
+
+/// Holds a bot's access token and refreshes it transparently when it nears
+/// expiry. Static-key bots never refresh; refreshable bots update the cached
+/// token behind a shared lock so concurrent callers reuse a single refresh.
+#[derive(Clone)]
+pub struct TokenManager {
+    inner: Arc<Mutex<TokenState>>,
+    client: reqwest::Client,
+}
+
+struct TokenState {
+    access_token: String,
+    /// `None` for static keys (never expires); `Some(unix_secs)` otherwise.
+    expires_at: Option<i64>,
+    refresh: Option<RefreshConfig>,
+}
+
+struct RefreshConfig {
+    refresh_token: String,
+    refresh_url: String,
+}
+
+#[derive(Deserialize)]
+struct RefreshResponse {
+    access_token: String,
+    /// Lifetime of the new token in seconds.
+    expires_in: i64,
+}
+
+impl TokenManager {
+    pub fn new(source: TokenSource) -> TokenManager {
+        let state = match source {
+            TokenSource::Static(access_token) => TokenState {
+                access_token,
+                expires_at: None,
+                refresh: None,
+            },
+            TokenSource::Refreshable {
+                access_token,
+                expires_at,
+                refresh_token,
+                refresh_url,
+            } => TokenState {
+                access_token,
+                expires_at: Some(expires_at),
+                refresh: Some(RefreshConfig {
+                    refresh_token,
+                    refresh_url,
+                }),
+            },
+        };
+        TokenManager {
+            inner: Arc::new(Mutex::new(state)),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Return a valid access token, refreshing first if it is within
+    /// [`REFRESH_WINDOW`] of expiry.
+    pub async fn token(&self) -> ApiResult<String> {
+        let mut state = self.inner.lock().await;
+        if state.needs_refresh() {
+            self.refresh(&mut state).await?;
+        }
+        Ok(state.access_token.clone())
+    }
+
+    async fn refresh(&self, state: &mut TokenState) -> ApiResult<()> {
+        let refresh = match &state.refresh {
+            Some(refresh) => refresh,
+            None => return Ok(()),
+        };
+        let response = self
+            .client
+            .post(&refresh.refresh_url)
+            .json(&serde_json::json!({
+                "grant_type": "refresh_token",
+                "refresh_token": refresh.refresh_token,
+            }))
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<RefreshResponse>()
+            .await?;
+
+        state.access_token = response.access_token;
+        state.expires_at = Some(now_unix() + response.expires_in);
+        Ok(())
+    }
+}
+
+impl TokenState {
+    fn needs_refresh(&self) -> bool {
+        match self.expires_at {
+            Some(expires_at) => now_unix() + REFRESH_WINDOW.as_secs() as i64 >= expires_at,
+            None => false,
+        }
+    }
+}
+
+fn now_unix() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+/// Outcome of submitting a move back to the server.
+#[derive(Clone, Debug, Deserialize)]
+pub struct SubmitResponse {
+    /// Whether the server accepted the move.
+    pub accepted: bool,
+    /// Optional message, e.g. explaining that the game state moved on.
+    #[serde(default)]
+    pub message: Option<String>,
+}
+
+/// Thin client over the hivegame.com bot API.
+pub struct HiveGameApi {
+    base_url: String,
+    client: reqwest::Client,
+}
+
+impl HiveGameApi {
+    pub fn new(base_url: String) -> HiveGameApi {
+        HiveGameApi {
+            base_url,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Fetch the game strings awaiting a move for the bot at `uri`.
+    pub async fn get_games(&self, uri: &str, token: &TokenManager) -> ApiResult<Vec<String>> {
+        let response = self
+            .client
+            .get(format!("{}{}", self.base_url, uri))
+            .bearer_auth(token.token().await?)
+            .send()
+            .await?
+            .error_for_status()?;
+        let games = response.json::<Vec<String>>().await?;
+        Ok(games)
+    }
+
+    /// Placeholder poll used in development before the real endpoint is wired
+    /// up; returns no games so the loop idles.
+    pub async fn fake_get_games(&self, _uri: &str, _token: &TokenManager) -> ApiResult<Vec<String>> {
+        Ok(Vec::new())
+    }
+
+    /// Submit a computed `bestmove` for `game_string` back to the server.
+    pub async fn submit_move(
+        &self,
+        uri: &str,
+        token: &TokenManager,
+        game_string: &str,
+        bestmove: &str,
+    ) -> ApiResult<SubmitResponse> {
+        let response = self
+            .client
+            .post(format!("{}{}/move", self.base_url, uri))
+            .bearer_auth(token.token().await?)
+            .json(&serde_json::json!({
+                "game_string": game_string,
+                "bestmove": bestmove,
+            }))
+            .send()
+            .await?
+            .error_for_status()?;
+        let result = response.json::<SubmitResponse>().await?;
+        Ok(result)
+    }
+}
@@ -1,59 +1,110 @@
-use std::process::{Command, Stdio, Child};
-use std::io::Write;
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Duration;
+
+use clap::{Parser, Subcommand};
 use tokio::sync::{mpsc, Mutex, Semaphore};
 
+mod config;
+use config::{Bot, Config};
 mod turn_tracker;
 use turn_tracker::{TurnTracker, TurnTracking};
 mod ai;
+mod persistence;
+use persistence::{RunState, Store};
+mod worker;
+use worker::{WorkerCommand, WorkerManager};
 mod hivegame_bot_api;
-use hivegame_bot_api::HiveGameApi;
-
-const MAX_CONCURRENT_PROCESSES: usize = 5;
-const QUEUE_CAPACITY: usize = 1000;
-const BASE_URL: &str = "http://your-server.com";
-
-#[derive(Clone)]
-struct Bot {
-    name: String,
-    uri: String,
-    api_key: String,
-    ai_command: String,
-    bestmove_command_args: String,
+use hivegame_bot_api::{HiveGameApi, TokenManager};
+
+/// UHP bot runner for hivegame.com.
+#[derive(Parser)]
+#[command(name = "hivegame-uhp-ai", about = "Run UHP engines as hivegame.com bots")]
+struct Cli {
+    /// Path to the TOML configuration file.
+    #[arg(short, long, default_value = "config.toml")]
+    config: PathBuf,
+
+    #[command(subcommand)]
+    command: Commands,
+}
+
+#[derive(Subcommand)]
+enum Commands {
+    /// Poll the server and play moves for every configured bot.
+    Run,
+    /// Check that the configuration is valid and every engine is runnable.
+    ValidateConfig,
+    /// Print the bots defined in the configuration.
+    ListBots,
 }
 
 struct GameTurn {
     game_string: String,
     hash: u64,
     bot: Bot,
+    /// Shared per-bot token manager, so the producer and the worker that
+    /// submits the move reuse a single cached (and refreshed) token.
+    token: TokenManager,
 }
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    let bots = vec![
-        Bot {
-            name: "nokamute1".to_string(),
-            uri: "/games/nokamute1".to_string(),
-            api_key: "nokamute1_key".to_string(),
-            ai_command: "../nokamute/target/debug/nokamute uhp --threads=1".to_string(),
-            bestmove_command_args: "depth 1".to_string(),
-        },
-        Bot {
-            name: "nokamute1".to_string(),
-            uri: "/games/nokamute2".to_string(),
-            api_key: "nokamute2_key".to_string(),
-            ai_command: "../nokamute/target/debug/nokamute uhp".to_string(),
-            bestmove_command_args: "time 00:00:01".to_string(),
-        },
-    ];
-
-    let (sender, receiver) = mpsc::channel(QUEUE_CAPACITY);
+    let cli = Cli::parse();
+    let config = Config::load(&cli.config)?;
+
+    match cli.command {
+        Commands::Run => run(config).await,
+        Commands::ValidateConfig => {
+            let problems = config.validate();
+            if problems.is_empty() {
+                println!("Configuration is valid: {} bot(s) ready", config.bots.len());
+                Ok(())
+            } else {
+                for problem in &problems {
+                    eprintln!("{}", problem);
+                }
+                Err(format!("{} problem(s) found in configuration", problems.len()).into())
+            }
+        }
+        Commands::ListBots => {
+            for bot in &config.bots {
+                println!("{}\t{}\t{}", bot.name, bot.uri, bot.ai_command);
+            }
+            Ok(())
+        }
+    }
+}
+
+async fn run(config: Config) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let (sender, receiver) = mpsc::channel(config.queue_capacity);
     let receiver = Arc::new(Mutex::new(receiver));
-    let semaphore = Arc::new(Semaphore::new(MAX_CONCURRENT_PROCESSES));
-    let active_processes = Arc::new(Mutex::new(Vec::new()));
+    let semaphore = Arc::new(Semaphore::new(config.max_concurrent));
+    let worker_manager = WorkerManager::new();
+    let (command_tx, mut command_rx) = mpsc::channel::<WorkerCommand>(64);
+    let engine_pool = ai::EnginePool::new(config.max_concurrent);
     let turn_tracker = TurnTracker::new();
-    
+    let base_url = config.base_url.clone();
+    let dev_fake_poll = config.dev_fake_poll;
+
+    // Optional durable run store. On startup, reset any turns left mid-flight by
+    // a previous process so they get re-picked.
+    let store = match &config.database_url {
+        Some(url) => {
+            let store = Store::connect(url).await?;
+            match store
+                .reset_stale_processing(Duration::from_secs(config.stale_after_secs))
+                .await
+            {
+                Ok(n) if n > 0 => println!("Reset {} stale processing run(s) for re-pickup", n),
+                Ok(_) => {}
+                Err(e) => eprintln!("Failed to reset stale runs: {}", e),
+            }
+            Some(store)
+        }
+        None => None,
+    };
+
     let cleanup_tracker = turn_tracker.clone();
     tokio::spawn(async move {
         loop {
@@ -62,22 +113,70 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
             cleanup_tracker.cleanup().await;
         }
     });
-    
+
+    // Apply operator commands (pause/resume/cancel) delivered on the channel.
+    let command_manager = worker_manager.clone();
+    tokio::spawn(async move {
+        while let Some(command) = command_rx.recv().await {
+            command_manager.command(command).await;
+        }
+    });
+
+    // Read operator commands from stdin, one per line:
+    //   pause <bot> | resume <bot> | cancel <worker-id>
+    tokio::spawn(read_operator_commands(command_tx));
+
+    // Periodically log the worker table so operators can see which bots are
+    // busy, idle, or have crashed.
+    let status_manager = worker_manager.clone();
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(Duration::from_secs(10)).await;
+            for info in status_manager.status().await {
+                match info.effective_interval {
+                    Some(interval) => println!(
+                        "worker #{} [{}] bot={} state={:?} interval={:.1}s",
+                        info.id,
+                        info.role,
+                        info.bot_name,
+                        info.state,
+                        interval.as_secs_f64()
+                    ),
+                    None => println!(
+                        "worker #{} [{}] bot={} state={:?}",
+                        info.id, info.role, info.bot_name, info.state
+                    ),
+                }
+            }
+        }
+    });
+
     // Spawn a producer task for each bot
     let mut producer_handles = Vec::new();
-    for bot in bots {
+    for bot in config.bots {
+        // One token manager per bot, shared across its producer and the workers
+        // that submit its moves.
+        let token = TokenManager::new(bot.api_key.clone());
         let producer_handle = tokio::spawn(producer_task(
             sender.clone(),
             turn_tracker.clone(),
+            store.clone(),
+            worker_manager.clone(),
+            token,
+            base_url.clone(),
+            dev_fake_poll,
             bot,
         ));
         producer_handles.push(producer_handle);
     }
-    
+
     let consumer_handle = tokio::spawn(consumer_task(
         receiver,
         semaphore,
-        active_processes,
+        worker_manager.clone(),
+        engine_pool,
+        store,
+        base_url.clone(),
         turn_tracker.clone(),
     ));
 
@@ -90,10 +189,95 @@ async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     if let Err(e) = consumer_handle.await? {
         eprintln!("Consumer error: {}", e);
     }
-    
+
     Ok(())
 }
 
+/// Read operator commands from stdin and forward them on the command channel.
+/// Accepts one command per line: `pause <bot>`, `resume <bot>`, or
+/// `cancel <worker-id>`. Unknown lines are reported and ignored.
+async fn read_operator_commands(command_tx: mpsc::Sender<WorkerCommand>) {
+    use tokio::io::{AsyncBufReadExt, BufReader};
+
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        let mut tokens = line.split_whitespace();
+        let command = match (tokens.next(), tokens.next()) {
+            (Some("pause"), Some(bot)) => WorkerCommand::Pause(bot.to_string()),
+            (Some("resume"), Some(bot)) => WorkerCommand::Resume(bot.to_string()),
+            (Some("cancel"), Some(id)) => match id.parse::<u64>() {
+                Ok(id) => WorkerCommand::Cancel(id),
+                Err(_) => {
+                    eprintln!("Invalid worker id '{}' for cancel", id);
+                    continue;
+                }
+            },
+            (Some(other), _) => {
+                eprintln!("Unknown operator command '{}'", other);
+                continue;
+            }
+            (None, _) => continue,
+        };
+        if command_tx.send(command).await.is_err() {
+            break;
+        }
+    }
+}
+
+/// Per-bot adaptive polling backoff. Idle cycles stretch the interval towards
+/// `max` (governed by `tranquility`); new turns or an error snap it back to
+/// `base`. A small jitter avoids many bots waking in lockstep.
+struct Backoff {
+    base: Duration,
+    max: Duration,
+    tranquility: f64,
+    current: Duration,
+    rng: u64,
+}
+
+impl Backoff {
+    fn new(base_secs: u64, max_secs: u64, tranquility: f64, seed: u64) -> Backoff {
+        let base = Duration::from_secs(base_secs.max(1));
+        Backoff {
+            base,
+            max: Duration::from_secs(max_secs.max(base_secs.max(1))),
+            tranquility: tranquility.max(1.0),
+            current: base,
+            // Avoid a zero seed, which would make the xorshift emit only zeros.
+            rng: seed | 1,
+        }
+    }
+
+    /// Feedback from the last cycle: `had_work` true means new turns were seen.
+    fn record(&mut self, had_work: bool) {
+        if had_work {
+            self.current = self.base;
+        } else {
+            let stretched = self.current.as_secs_f64() * self.tranquility;
+            self.current = Duration::from_secs_f64(stretched).min(self.max);
+        }
+    }
+
+    /// On an error, poll eagerly again at the base interval.
+    fn reset(&mut self) {
+        self.current = self.base;
+    }
+
+    /// Current interval with ±12.5% jitter applied.
+    fn sleep_duration(&mut self) -> Duration {
+        // xorshift64 keeps us free of an RNG dependency.
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 7;
+        self.rng ^= self.rng << 17;
+        let factor = 0.875 + (self.rng >> 40) as f64 / (1u64 << 24) as f64 * 0.25;
+        self.current.mul_f64(factor)
+    }
+
+    fn current(&self) -> Duration {
+        self.current
+    }
+}
+
 fn calculate_hash(game_string: &str) -> u64 {
     use std::hash::{Hash, Hasher};
     use std::collections::hash_map::DefaultHasher;
@@ -105,24 +289,70 @@ fn calculate_hash(game_string: &str) -> u64 {
 async fn producer_task(
     sender: mpsc::Sender<GameTurn>,
     turn_tracker: TurnTracker,
+    store: Option<Store>,
+    worker_manager: WorkerManager,
+    token: TokenManager,
+    base_url: String,
+    dev_fake_poll: bool,
     bot: Bot,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    let api = HiveGameApi::new(BASE_URL.to_string());
-    
+    let api = HiveGameApi::new(base_url);
+    let worker = worker_manager.register("producer", &bot.name).await;
+    let mut backoff = Backoff::new(
+        bot.base_interval_secs,
+        bot.max_interval_secs,
+        bot.tranquility,
+        calculate_hash(&bot.name),
+    );
+
     loop {
-        match api.fake_get_games(&bot.uri, &bot.api_key).await {
+        // Honour an operator pause without polling the game API.
+        if worker_manager.is_paused(&bot.name).await {
+            worker.idle().await;
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            continue;
+        }
+
+        worker.active().await;
+        let mut new_turns = 0usize;
+        let games = if dev_fake_poll {
+            api.fake_get_games(&bot.uri, &token).await
+        } else {
+            api.get_games(&bot.uri, &token).await
+        };
+        match games {
             Ok(game_strings) => {
                 for game_string in game_strings {
                     let hash = calculate_hash(&game_string);
-                    
+
+                    // Fast in-memory front cache avoids even touching the DB for
+                    // turns this process has already seen.
                     if turn_tracker.tracked(hash).await {
                         continue;
                     }
 
+                    // Durable dedup: skip hashes already present in a non-failed
+                    // state, and only queue turns for which we inserted a fresh
+                    // Pending row.
+                    if let Some(store) = &store {
+                        match store.insert_pending(hash, &bot.name, &game_string).await {
+                            Ok(false) => {
+                                turn_tracker.processing(hash).await;
+                                continue;
+                            }
+                            Ok(true) => {}
+                            Err(e) => {
+                                eprintln!("Failed to persist pending run for bot {}: {}", bot.name, e);
+                                continue;
+                            }
+                        }
+                    }
+
                     let turn = GameTurn {
                         game_string,
                         hash,
                         bot: bot.clone(),
+                        token: token.clone(),
                     };
 
                     turn_tracker.processing(hash).await;
@@ -131,69 +361,185 @@ async fn producer_task(
                         eprintln!("Failed to send turn to queue");
                         continue;
                     }
+                    new_turns += 1;
                 }
+                backoff.record(new_turns > 0);
+                // Only a clean cycle returns the worker to Idle; an errored
+                // cycle keeps its Dead(reason) visible in the status table.
+                worker.idle().await;
+            }
+            Err(e) => {
+                worker.dead(format!("fetch failed: {}", e)).await;
+                eprintln!("Failed to fetch games for bot {}: {}", bot.name, e);
+                // Errors snap polling back to the fast interval for a retry.
+                backoff.reset();
             }
-            Err(e) => eprintln!("Failed to fetch games for bot {}: {}", bot.name, e),
         }
 
-        println!("Start new cycle in 1 sec");
-        tokio::time::sleep(Duration::from_secs(1)).await;
+        worker.set_interval(backoff.current()).await;
+        let sleep_for = backoff.sleep_duration();
+        println!(
+            "Bot '{}' sleeping {:.1}s before next cycle",
+            bot.name,
+            sleep_for.as_secs_f64()
+        );
+        tokio::time::sleep(sleep_for).await;
     }
 }
 
 async fn consumer_task(
     receiver: Arc<Mutex<mpsc::Receiver<GameTurn>>>,
     semaphore: Arc<Semaphore>,
-    active_processes: Arc<Mutex<Vec<tokio::task::JoinHandle<()>>>>,
+    worker_manager: WorkerManager,
+    engine_pool: ai::EnginePool,
+    store: Option<Store>,
+    base_url: String,
     turn_tracker: TurnTracker,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     loop {
         let mut rx = receiver.lock().await;
         if let Some(turn) = rx.recv().await {
             drop(rx);
-            
-            let handle = tokio::spawn(process_turn(
+
+            // Each in-flight turn is a supervised worker; the manager prunes it
+            // when the task's WorkerHandle drops.
+            tokio::spawn(process_turn(
                 turn,
                 semaphore.clone(),
+                worker_manager.clone(),
+                engine_pool.clone(),
+                store.clone(),
+                base_url.clone(),
                 turn_tracker.clone(),
             ));
+        }
+    }
+}
+
+/// Submit a move with bounded exponential backoff. The first success wins; the
+/// last error is returned if every attempt fails.
+async fn submit_with_retry(
+    api: &HiveGameApi,
+    token: &TokenManager,
+    turn: &GameTurn,
+    bestmove: &str,
+) -> Result<hivegame_bot_api::SubmitResponse, Box<dyn std::error::Error + Send + Sync>> {
+    const MAX_ATTEMPTS: u32 = 5;
+    let mut delay = Duration::from_millis(200);
 
-            active_processes.lock().await.push(handle);
-            cleanup_processes(active_processes.clone()).await;
+    let mut last_err = None;
+    for attempt in 1..=MAX_ATTEMPTS {
+        match api
+            .submit_move(&turn.bot.uri, token, &turn.game_string, bestmove)
+            .await
+        {
+            Ok(response) => return Ok(response),
+            Err(e) => {
+                eprintln!(
+                    "Submit attempt {}/{} failed for bot '{}': {}",
+                    attempt, MAX_ATTEMPTS, turn.bot.name, e
+                );
+                last_err = Some(e);
+                if attempt < MAX_ATTEMPTS {
+                    tokio::time::sleep(delay).await;
+                    delay = (delay * 2).min(Duration::from_secs(10));
+                }
+            }
         }
     }
+
+    Err(last_err.expect("at least one attempt was made"))
 }
 
 async fn process_turn(
     turn: GameTurn,
     semaphore: Arc<Semaphore>,
+    worker_manager: WorkerManager,
+    engine_pool: ai::EnginePool,
+    store: Option<Store>,
+    base_url: String,
     turn_tracker: TurnTracker,
 ) {
     let _permit = semaphore.acquire().await.expect("Failed to acquire semaphore");
+    let worker = worker_manager.register("process_turn", &turn.bot.name).await;
 
-    let child = match ai::spawn_process(&turn.bot.ai_command, &turn.bot.name) {
-        Ok(child) => child,
-        Err(e) => {
-            eprintln!("Failed to spawn AI process for bot {}: {}", turn.bot.name, e);
-            turn_tracker.processed(turn.hash).await;
-            return;
+    // An operator can cancel a queued-but-not-started turn before we spend an
+    // engine on it. Mark it Failed so it is re-queued rather than stranded at
+    // Pending in the durable store.
+    if worker.is_cancelled() {
+        worker.dead("cancelled before start").await;
+        if let Some(store) = &store {
+            if let Err(e) = store.set_state(turn.hash, RunState::Failed, None).await {
+                eprintln!("Failed to mark cancelled run failed for bot {}: {}", turn.bot.name, e);
+            }
         }
-    };
+        turn_tracker.processed(turn.hash).await;
+        return;
+    }
+    worker.active().await;
+
+    if let Some(store) = &store {
+        if let Err(e) = store.set_state(turn.hash, RunState::Processing, None).await {
+            eprintln!("Failed to mark run processing for bot {}: {}", turn.bot.name, e);
+        }
+    }
 
-    match ai::run_commands(child, &turn.game_string, &turn.bot.bestmove_command_args).await {
+    match ai::run_commands(
+        &engine_pool,
+        &turn.bot.name,
+        &turn.bot.ai_command,
+        &turn.game_string,
+        &turn.bot.bestmove_command_args,
+        worker.cancel_token(),
+    )
+    .await
+    {
         Ok(bestmove) => {
             println!("Bot '{}' bestmove: '{}'", turn.bot.name, bestmove);
-            // Here you can handle the bestmove (e.g., send it to the server)
+            let api = HiveGameApi::new(base_url);
+
+            match submit_with_retry(&api, &turn.token, &turn, &bestmove).await {
+                Ok(response) => {
+                    println!(
+                        "Bot '{}' move submitted (accepted={}): {}",
+                        turn.bot.name,
+                        response.accepted,
+                        response.message.as_deref().unwrap_or("-")
+                    );
+                    worker.idle().await;
+                    if let Some(store) = &store {
+                        if let Err(e) = store
+                            .set_state(turn.hash, RunState::Completed, Some(&bestmove))
+                            .await
+                        {
+                            eprintln!("Failed to mark run completed for bot {}: {}", turn.bot.name, e);
+                        }
+                    }
+                }
+                Err(e) => {
+                    // Mark the turn for retry rather than dropping it: leave the
+                    // durable state Failed (re-picked by the producer) and let
+                    // the front cache expire so it can be re-queued.
+                    worker.dead(format!("submit failed: {}", e)).await;
+                    eprintln!("Failed to submit move for bot '{}': {}", turn.bot.name, e);
+                    if let Some(store) = &store {
+                        if let Err(e) = store.set_state(turn.hash, RunState::Failed, None).await {
+                            eprintln!("Failed to mark run failed for bot {}: {}", turn.bot.name, e);
+                        }
+                    }
+                }
+            }
         }
         Err(e) => {
+            worker.dead(format!("engine error: {}", e)).await;
             eprintln!("Error running AI commands for bot '{}': '{}'", turn.bot.name, e);
+            if let Some(store) = &store {
+                if let Err(e) = store.set_state(turn.hash, RunState::Failed, None).await {
+                    eprintln!("Failed to mark run failed for bot {}: {}", turn.bot.name, e);
+                }
+            }
         }
     }
 
     turn_tracker.processed(turn.hash).await;
 }
-
-async fn cleanup_processes(active_processes: Arc<Mutex<Vec<tokio::task::JoinHandle<()>>>>) {
-    let mut processes = active_processes.lock().await;
-    processes.retain(|handle| !handle.is_finished());
-}
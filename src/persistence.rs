@@ -0,0 +1,139 @@
+use std::time::Duration;
+
+use bb8::Pool;
+use bb8_postgres::PostgresConnectionManager;
+use tokio_postgres::NoTls;
+
+type PgPool = Pool<PostgresConnectionManager<NoTls>>;
+
+/// Lifecycle of a single tracked turn, persisted in the `runs` table.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RunState {
+    Pending,
+    Processing,
+    Completed,
+    Failed,
+}
+
+impl RunState {
+    fn as_str(self) -> &'static str {
+        match self {
+            RunState::Pending => "pending",
+            RunState::Processing => "processing",
+            RunState::Completed => "completed",
+            RunState::Failed => "failed",
+        }
+    }
+}
+
+/// Durable job/run store backed by Postgres, modeled on a CI driver's job
+/// tables. Rows are keyed by the turn `hash` so a restart does not re-process or
+/// double-submit moves that are already in flight or done.
+#[derive(Clone)]
+pub struct Store {
+    pool: PgPool,
+}
+
+impl Store {
+    /// Connect to Postgres and ensure the `runs` table exists.
+    pub async fn connect(
+        database_url: &str,
+    ) -> Result<Store, Box<dyn std::error::Error + Send + Sync>> {
+        let manager = PostgresConnectionManager::new_from_stringlike(database_url, NoTls)?;
+        let pool = Pool::builder().build(manager).await?;
+        let store = Store { pool };
+        store.migrate().await?;
+        Ok(store)
+    }
+
+    async fn migrate(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let conn = self.pool.get().await?;
+        conn.batch_execute(
+            "CREATE TABLE IF NOT EXISTS runs (
+                hash         BIGINT PRIMARY KEY,
+                bot_name     TEXT NOT NULL,
+                game_string  TEXT NOT NULL,
+                state        TEXT NOT NULL,
+                bestmove     TEXT,
+                created_at   TIMESTAMPTZ NOT NULL DEFAULT now(),
+                updated_at   TIMESTAMPTZ NOT NULL DEFAULT now()
+            )",
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Insert a `Pending` row for a turn, skipping any hash already present in a
+    /// non-failed state. Returns `true` when a new row was inserted or a
+    /// `Failed` row was reset to `Pending` (i.e. the turn should be queued).
+    pub async fn insert_pending(
+        &self,
+        hash: u64,
+        bot_name: &str,
+        game_string: &str,
+    ) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+        let conn = self.pool.get().await?;
+        let rows = conn
+            .execute(
+                "INSERT INTO runs (hash, bot_name, game_string, state)
+                 VALUES ($1, $2, $3, $4)
+                 ON CONFLICT (hash) DO UPDATE SET
+                     bot_name = EXCLUDED.bot_name,
+                     game_string = EXCLUDED.game_string,
+                     state = EXCLUDED.state,
+                     updated_at = now()
+                 WHERE runs.state = 'failed'",
+                &[
+                    &(hash as i64),
+                    &bot_name,
+                    &game_string,
+                    &RunState::Pending.as_str(),
+                ],
+            )
+            .await?;
+        Ok(rows == 1)
+    }
+
+    /// Transition a turn to a new state, optionally recording the computed
+    /// bestmove when completing.
+    pub async fn set_state(
+        &self,
+        hash: u64,
+        state: RunState,
+        bestmove: Option<&str>,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let conn = self.pool.get().await?;
+        conn.execute(
+            "UPDATE runs
+             SET state = $2,
+                 bestmove = COALESCE($3, bestmove),
+                 updated_at = now()
+             WHERE hash = $1",
+            &[&(hash as i64), &state.as_str(), &bestmove],
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Reset `Processing` rows older than `stale_after` to `Failed` so a crashed
+    /// worker's turns get re-queued on restart: `insert_pending` re-inserts over
+    /// `Failed` rows, so marking them `Failed` (rather than `Pending`, which the
+    /// upsert treats as already-present and skips) is what actually replays them
+    /// the next time the producer polls the same `game_string`.
+    pub async fn reset_stale_processing(
+        &self,
+        stale_after: Duration,
+    ) -> Result<u64, Box<dyn std::error::Error + Send + Sync>> {
+        let conn = self.pool.get().await?;
+        let rows = conn
+            .execute(
+                "UPDATE runs
+                 SET state = 'failed', updated_at = now()
+                 WHERE state = 'processing'
+                   AND updated_at < now() - make_interval(secs => $1)",
+                &[&(stale_after.as_secs() as f64)],
+            )
+            .await?;
+        Ok(rows)
+    }
+}
@@ -0,0 +1,253 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::Mutex;
+
+/// Liveness of a registered worker, surfaced in the status table.
+#[derive(Clone, Debug)]
+pub enum WorkerState {
+    /// Doing work right now (polling or computing a move).
+    Active,
+    /// Alive but waiting for work.
+    Idle,
+    /// Stopped because of the contained reason.
+    Dead(String),
+}
+
+/// A point-in-time snapshot of one worker, returned by [`WorkerManager::status`].
+#[derive(Clone, Debug)]
+pub struct WorkerInfo {
+    pub id: u64,
+    pub role: String,
+    pub bot_name: String,
+    pub state: WorkerState,
+    /// Current effective polling interval, for workers that throttle themselves
+    /// (producers). `None` for workers without an adaptive interval.
+    pub effective_interval: Option<Duration>,
+}
+
+/// Operator command delivered over the manager's command channel.
+#[derive(Clone, Debug)]
+pub enum WorkerCommand {
+    /// Stop a bot's producer from polling.
+    Pause(String),
+    /// Resume a previously paused bot's producer.
+    Resume(String),
+    /// Request cancellation of a specific in-flight worker.
+    Cancel(u64),
+}
+
+struct WorkerEntry {
+    role: String,
+    bot_name: String,
+    state: WorkerState,
+    cancel: Arc<AtomicBool>,
+    effective_interval: Option<Duration>,
+}
+
+/// How many recent `Dead` workers to retain for the status table.
+const DEAD_HISTORY_CAP: usize = 64;
+
+struct Inner {
+    workers: HashMap<u64, WorkerEntry>,
+    paused: HashSet<String>,
+    /// Bounded ring of recently-crashed workers, retained after their handles
+    /// drop so `status()` can still surface failures.
+    dead_history: Vec<WorkerInfo>,
+}
+
+/// Central supervisor tracking every producer and in-flight `process_turn` as a
+/// registered worker, and accepting operator commands to pause, resume, or
+/// cancel work.
+#[derive(Clone)]
+pub struct WorkerManager {
+    inner: Arc<Mutex<Inner>>,
+    next_id: Arc<AtomicU64>,
+}
+
+/// Handle held by a running worker; updates its state and unregisters on drop.
+pub struct WorkerHandle {
+    id: u64,
+    manager: WorkerManager,
+    cancel: Arc<AtomicBool>,
+}
+
+impl WorkerManager {
+    pub fn new() -> WorkerManager {
+        WorkerManager {
+            inner: Arc::new(Mutex::new(Inner {
+                workers: HashMap::new(),
+                paused: HashSet::new(),
+                dead_history: Vec::new(),
+            })),
+            next_id: Arc::new(AtomicU64::new(1)),
+        }
+    }
+
+    /// Register a worker with a human-readable `role` (e.g. `"producer"` or
+    /// `"process_turn"`) bound to `bot_name`. The worker starts [`Idle`].
+    ///
+    /// [`Idle`]: WorkerState::Idle
+    pub async fn register(&self, role: &str, bot_name: &str) -> WorkerHandle {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let cancel = Arc::new(AtomicBool::new(false));
+        self.inner.lock().await.workers.insert(
+            id,
+            WorkerEntry {
+                role: role.to_string(),
+                bot_name: bot_name.to_string(),
+                state: WorkerState::Idle,
+                cancel: cancel.clone(),
+                effective_interval: None,
+            },
+        );
+        WorkerHandle {
+            id,
+            manager: self.clone(),
+            cancel,
+        }
+    }
+
+    async fn set_state(&self, id: u64, state: WorkerState) {
+        let mut inner = self.inner.lock().await;
+        // Record crashes in the bounded history so they remain visible even
+        // after the handle drops or a retrying producer flips back to Active.
+        if let WorkerState::Dead(_) = state {
+            if let Some(entry) = inner.workers.get(&id) {
+                let info = WorkerInfo {
+                    id,
+                    role: entry.role.clone(),
+                    bot_name: entry.bot_name.clone(),
+                    state: state.clone(),
+                    effective_interval: entry.effective_interval,
+                };
+                if inner.dead_history.len() >= DEAD_HISTORY_CAP {
+                    inner.dead_history.remove(0);
+                }
+                inner.dead_history.push(info);
+            }
+        }
+        if let Some(entry) = inner.workers.get_mut(&id) {
+            entry.state = state;
+        }
+    }
+
+    async fn set_interval(&self, id: u64, interval: Duration) {
+        if let Some(entry) = self.inner.lock().await.workers.get_mut(&id) {
+            entry.effective_interval = Some(interval);
+        }
+    }
+
+    async fn unregister(&self, id: u64) {
+        self.inner.lock().await.workers.remove(&id);
+    }
+
+    /// Apply an operator command.
+    pub async fn command(&self, command: WorkerCommand) {
+        let mut inner = self.inner.lock().await;
+        match command {
+            WorkerCommand::Pause(bot) => {
+                inner.paused.insert(bot);
+            }
+            WorkerCommand::Resume(bot) => {
+                inner.paused.remove(&bot);
+            }
+            WorkerCommand::Cancel(id) => {
+                if let Some(entry) = inner.workers.get(&id) {
+                    entry.cancel.store(true, Ordering::Relaxed);
+                }
+            }
+        }
+    }
+
+    /// Whether a bot's producer is currently paused.
+    pub async fn is_paused(&self, bot_name: &str) -> bool {
+        self.inner.lock().await.paused.contains(bot_name)
+    }
+
+    /// Current worker table (live workers plus recently-crashed ones retained in
+    /// the dead history), sorted by id for stable output.
+    pub async fn status(&self) -> Vec<WorkerInfo> {
+        let inner = self.inner.lock().await;
+        let mut infos: Vec<WorkerInfo> = inner
+            .workers
+            .iter()
+            .map(|(id, entry)| WorkerInfo {
+                id: *id,
+                role: entry.role.clone(),
+                bot_name: entry.bot_name.clone(),
+                state: entry.state.clone(),
+                effective_interval: entry.effective_interval,
+            })
+            .collect();
+        // Add crashed workers no longer present as live entries.
+        for info in &inner.dead_history {
+            if !inner.workers.contains_key(&info.id) {
+                infos.push(info.clone());
+            }
+        }
+        infos.sort_by_key(|info| info.id);
+        infos
+    }
+}
+
+impl Default for WorkerManager {
+    fn default() -> WorkerManager {
+        WorkerManager::new()
+    }
+}
+
+impl WorkerHandle {
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Whether this worker has been asked to cancel.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.load(Ordering::Relaxed)
+    }
+
+    /// Shared cancellation flag, so a blocking worker (e.g. an engine search)
+    /// can observe cancellation requested after it started.
+    pub fn cancel_token(&self) -> Arc<AtomicBool> {
+        self.cancel.clone()
+    }
+
+    pub async fn active(&self) {
+        self.manager.set_state(self.id, WorkerState::Active).await;
+    }
+
+    pub async fn idle(&self) {
+        self.manager.set_state(self.id, WorkerState::Idle).await;
+    }
+
+    /// Record this worker's current effective polling interval so it shows up in
+    /// the status table.
+    pub async fn set_interval(&self, interval: Duration) {
+        self.manager.set_interval(self.id, interval).await;
+    }
+
+    /// Report a fatal error; the worker is left in the table as `Dead` so the
+    /// failure is visible in the status query rather than lost to `eprintln!`.
+    pub async fn dead(&self, reason: impl Into<String>) {
+        self.manager
+            .set_state(self.id, WorkerState::Dead(reason.into()))
+            .await;
+    }
+}
+
+impl Drop for WorkerHandle {
+    fn drop(&mut self) {
+        // Remove without blocking; if the lock is contended, removal is
+        // deferred to a spawned task.
+        if let Ok(mut inner) = self.manager.inner.try_lock() {
+            inner.workers.remove(&self.id);
+        } else {
+            let manager = self.manager.clone();
+            let id = self.id;
+            tokio::spawn(async move { manager.unregister(id).await });
+        }
+    }
+}